@@ -1,27 +1,45 @@
 use crate::camera::Camera;
 use crate::defs::{self, Result};
 use crate::error::Error;
+use crate::palette;
 use crate::shader::Shader;
 
 use log::error;
+#[cfg(not(target_arch = "wasm32"))]
+use pixels::wgpu::util::DeviceExt as _;
 use pixels::{wgpu, Pixels, SurfaceTexture};
 use std::cell::{Ref, RefCell, RefMut};
 use std::sync::Arc;
 use winit::application::ApplicationHandler;
 use winit::dpi::PhysicalSize;
-use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
-use winit::event_loop::ActiveEventLoop;
+use winit::event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, EventLoopProxy};
+use winit::keyboard::{KeyCode, PhysicalKey};
 use winit::window::{CursorIcon, Window};
 
+#[cfg(target_arch = "wasm32")]
+use winit::platform::web::WindowExtWebSys;
+
+/// Events sent back into the app once asynchronous (`wasm32`) initialization completes.
+pub enum AppEvent {
+    PixelsReady(Pixels<'static>),
+}
+
 #[derive(Default)]
 pub struct App<'a> {
     window: Option<Arc<Window>>,
     shader: Option<RefCell<Shader>>,
     camera: Option<RefCell<Camera>>,
     pixels: Option<RefCell<Pixels<'a>>>,
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    event_loop_proxy: Option<EventLoopProxy<AppEvent>>,
+    /// Whether moving the cursor re-picks `julia_c` in Julia mode (see [`Self::update_julia_c`]).
+    /// Toggled with `KeyC` so a chosen Julia set can be frozen and then panned/zoomed into.
+    picking_julia_c: bool,
 }
 
-impl ApplicationHandler for App<'_> {
+impl ApplicationHandler<AppEvent> for App<'_> {
+    #[cfg(not(target_arch = "wasm32"))]
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         if let Err(e) = self.init(event_loop) {
             error!("Failed to initialize app: {e}");
@@ -29,6 +47,74 @@ impl ApplicationHandler for App<'_> {
         }
     }
 
+    /// On the web the adapter/device request is asynchronous, so initialization is kicked off
+    /// here and finishes later via [`AppEvent::PixelsReady`] instead of blocking this callback.
+    #[cfg(target_arch = "wasm32")]
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        let window = match event_loop.create_window(defs::init_window()) {
+            Ok(window) => Arc::new(window),
+            Err(e) => {
+                error!("Failed to create window: {e}");
+                event_loop.exit();
+                return;
+            }
+        };
+
+        Self::attach_canvas(&window);
+
+        let proxy = self
+            .event_loop_proxy
+            .clone()
+            .expect("App was constructed without an event loop proxy");
+
+        self.window = Some(window.clone());
+
+        wasm_bindgen_futures::spawn_local(async move {
+            let size = window.inner_size();
+            let surface_texture = SurfaceTexture::new(size.width, size.height, window);
+
+            let pixels = pixels::PixelsBuilder::new(size.width, size.height, surface_texture)
+                .device_descriptor(wgpu::DeviceDescriptor {
+                    label: None,
+                    required_features: wgpu::Features::empty(),
+                    required_limits: wgpu::Limits::downlevel_webgl2_defaults()
+                        .using_resolution(wgpu::Limits::default()),
+                })
+                .build_async()
+                .await
+                .expect("Failed to build pixels surface");
+
+            let _ = proxy.send_event(AppEvent::PixelsReady(pixels));
+        });
+    }
+
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: AppEvent) {
+        let AppEvent::PixelsReady(pixels) = event;
+
+        let size = match self.window() {
+            Ok(window) => window.inner_size(),
+            Err(e) => {
+                error!("Failed to get window: {e}");
+                event_loop.exit();
+                return;
+            }
+        };
+
+        self.shader = Some(RefCell::new(Shader::new(size, &pixels)));
+        self.camera = Some(RefCell::new(Camera::new(size)));
+        self.pixels = Some(RefCell::new(pixels));
+
+        if let Err(e) = self.render() {
+            error!("Failed to draw: {e}");
+            event_loop.exit();
+            return;
+        }
+
+        if let Ok(window) = self.window() {
+            window.set_visible(true);
+        }
+    }
+
     fn window_event(
         &mut self,
         event_loop: &winit::event_loop::ActiveEventLoop,
@@ -51,7 +137,16 @@ impl ApplicationHandler for App<'_> {
             }
             // TODO: Implement changing the number of iterations.
             // TODO: Implement changing the color scheme.
-            // TODO: Implement saving the fractal to an image file.
+            WindowEvent::KeyboardInput {
+                device_id: _,
+                event,
+                is_synthetic: _,
+            } => {
+                if let Err(e) = self.handle_key(event) {
+                    error!("Failed to handle key: {e}");
+                    event_loop.exit();
+                }
+            }
             WindowEvent::CursorMoved {
                 device_id: _,
                 position,
@@ -67,6 +162,12 @@ impl ApplicationHandler for App<'_> {
                 if let Err(e) = self.update_mouse_position(position) {
                     error!("Failed to update mouse position: {e}");
                     event_loop.exit();
+                    return;
+                }
+
+                if let Err(e) = self.update_julia_c() {
+                    error!("Failed to update julia_c: {e}");
+                    event_loop.exit();
                 }
             }
             WindowEvent::MouseInput {
@@ -103,12 +204,19 @@ impl ApplicationHandler for App<'_> {
             _ => (),
         }
     }
+
+    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Err(e) = self.tick() {
+            error!("Failed to tick: {e}");
+        }
+    }
 }
 
 impl App<'_> {
     /// Create a new app with the given number of iterations.
-    pub fn new(_iterations: u32) -> Self {
+    pub fn new(_iterations: u32, event_loop_proxy: EventLoopProxy<AppEvent>) -> Self {
         Self {
+            event_loop_proxy: Some(event_loop_proxy),
             ..Default::default()
         }
     }
@@ -160,6 +268,7 @@ impl<'a> App<'a> {
 
 impl App<'_> {
     /// Initialize the app.
+    #[cfg(not(target_arch = "wasm32"))]
     fn init(&mut self, event_loop: &ActiveEventLoop) -> Result<()> {
         let window = Arc::new(event_loop.create_window(defs::init_window())?);
 
@@ -184,6 +293,18 @@ impl App<'_> {
         Ok(())
     }
 
+    /// Attach the winit window's canvas to the document body so it renders on the page.
+    #[cfg(target_arch = "wasm32")]
+    fn attach_canvas(window: &Window) {
+        let canvas = window.canvas().expect("Window was not backed by a canvas");
+
+        web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.body())
+            .and_then(|body| body.append_child(&canvas).ok())
+            .expect("Failed to attach canvas to document body");
+    }
+
     /// Render the app, drawing the fractal to the pixels buffer.
     fn render(&mut self) -> Result<()> {
         let pixels = self.pixels()?;
@@ -264,6 +385,10 @@ impl App<'_> {
         shader.params.set_scale(camera.scale);
         shader.params.set_center(camera.world_position);
 
+        if shader.reference_orbit_stale(camera.world_position, camera.scale) {
+            shader.set_reference_orbit(self.pixels()?.queue(), camera.world_position);
+        }
+
         self.pixels()?.queue().write_buffer(
             &shader.uniform_buffer,
             0,
@@ -315,6 +440,10 @@ impl App<'_> {
 
         shader.params.set_center(camera.world_position);
 
+        if shader.reference_orbit_stale(camera.world_position, camera.scale) {
+            shader.set_reference_orbit(pixels.queue(), camera.world_position);
+        }
+
         pixels.queue().write_buffer(
             &shader.uniform_buffer,
             0,
@@ -325,4 +454,373 @@ impl App<'_> {
 
         Ok(())
     }
+
+    /// Update the held-key state of the camera in response to a keyboard event.
+    pub fn handle_key(&mut self, event: KeyEvent) -> Result<()> {
+        let PhysicalKey::Code(code) = event.physical_key else {
+            return Ok(());
+        };
+
+        let pressed = event.state == ElementState::Pressed;
+
+        if pressed && !event.repeat && code == KeyCode::KeyP {
+            return self.cycle_palette();
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if pressed && !event.repeat && code == KeyCode::KeyS {
+            let size = self.window()?.inner_size();
+
+            return self.export_png(
+                defs::EXPORT_PATH,
+                size.width * defs::EXPORT_SCALE,
+                size.height * defs::EXPORT_SCALE,
+                defs::EXPORT_SAMPLES,
+            );
+        }
+
+        if pressed && !event.repeat && code == KeyCode::KeyJ {
+            return self.toggle_fractal_kind();
+        }
+
+        if pressed && !event.repeat && code == KeyCode::KeyC {
+            self.picking_julia_c = !self.picking_julia_c;
+            return Ok(());
+        }
+
+        let mut camera = self.camera_mut()?;
+
+        match code {
+            KeyCode::ArrowLeft => camera.is_left_pressed = pressed,
+            KeyCode::ArrowRight => camera.is_right_pressed = pressed,
+            KeyCode::ArrowUp => camera.is_up_pressed = pressed,
+            KeyCode::ArrowDown => camera.is_down_pressed = pressed,
+            KeyCode::Equal | KeyCode::NumpadAdd => camera.is_zoom_in_pressed = pressed,
+            KeyCode::Minus | KeyCode::NumpadSubtract => camera.is_zoom_out_pressed = pressed,
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    /// Advance continuous keyboard-driven camera motion and the palette's color cycling, then
+    /// redraw. The color offset always advances, so this redraws every tick regardless of
+    /// whether the camera itself moved.
+    fn tick(&mut self) -> Result<()> {
+        let mut camera = self.camera_mut()?;
+
+        let (moved, dt) = camera.update();
+
+        let mut shader = self.shader_mut()?;
+
+        if moved {
+            shader.params.set_scale(camera.scale);
+            shader.params.set_center(camera.world_position);
+
+            if shader.reference_orbit_stale(camera.world_position, camera.scale) {
+                shader.set_reference_orbit(self.pixels()?.queue(), camera.world_position);
+            }
+        }
+
+        let color_offset = shader.params.color_offset() + palette::CYCLE_SPEED * dt as f32;
+        shader.params.set_color_offset(color_offset.fract());
+
+        self.pixels()?.queue().write_buffer(
+            &shader.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[shader.params]),
+        );
+
+        drop(camera);
+        drop(shader);
+
+        self.window()?.request_redraw();
+
+        Ok(())
+    }
+
+    /// Cycle to the next built-in gradient palette.
+    fn cycle_palette(&mut self) -> Result<()> {
+        let mut shader = self.shader_mut()?;
+
+        let next_palette = (shader.params.palette_id() + 1) % palette::PALETTE_COUNT;
+        shader.params.set_palette(next_palette);
+
+        self.pixels()?.queue().write_buffer(
+            &shader.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[shader.params]),
+        );
+
+        self.window()?.request_redraw();
+
+        Ok(())
+    }
+
+    /// Toggle between rendering the Mandelbrot set and a live Julia set.
+    fn toggle_fractal_kind(&mut self) -> Result<()> {
+        let mut shader = self.shader_mut()?;
+
+        let next_kind = if shader.params.fractal_kind() == defs::FRACTAL_MANDELBROT {
+            defs::FRACTAL_JULIA
+        } else {
+            defs::FRACTAL_MANDELBROT
+        };
+
+        shader.params.set_fractal_kind(next_kind);
+
+        self.pixels()?.queue().write_buffer(
+            &shader.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[shader.params]),
+        );
+
+        // Entering Julia mode starts out picking, so the set tracks the cursor until the user
+        // freezes it with `KeyC`.
+        self.picking_julia_c = next_kind == defs::FRACTAL_JULIA;
+
+        self.window()?.request_redraw();
+
+        Ok(())
+    }
+
+    /// While in Julia mode and picking (see [`Self::picking_julia_c`]), sync `julia_c` to the
+    /// point under the cursor so the Julia set morphs live as the user hovers over the
+    /// corresponding point of the Mandelbrot set. Toggling picking off freezes `julia_c` so the
+    /// frozen set can be panned/zoomed into without the cursor re-picking it.
+    fn update_julia_c(&mut self) -> Result<()> {
+        if self.shader()?.params.fractal_kind() != defs::FRACTAL_JULIA || !self.picking_julia_c {
+            return Ok(());
+        }
+
+        let julia_c = self.camera_mut()?.mouse_world_position();
+
+        let mut shader = self.shader_mut()?;
+        shader.params.set_julia_c(julia_c);
+
+        self.pixels()?.queue().write_buffer(
+            &shader.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[shader.params]),
+        );
+
+        self.window()?.request_redraw();
+
+        Ok(())
+    }
+
+    /// Render the current view offscreen at `width`x`height` (supersampled `samples`x per axis)
+    /// and save it as a lossless PNG at `path`.
+    ///
+    /// Native-only: this blocks on `device.poll(wgpu::Maintain::Wait)` and a channel `recv()`
+    /// while the GPU readback completes, which would hang the browser's main thread on wasm32.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn export_png(&mut self, path: &str, width: u32, height: u32, samples: u32) -> Result<()> {
+        let pixels = self.pixels()?;
+        let shader = self.shader()?;
+
+        let device = pixels.device();
+        let queue = pixels.queue();
+
+        let ssaa_width = width * samples;
+        let ssaa_height = height * samples;
+
+        let mut export_params = shader.params;
+        export_params.set_size(PhysicalSize::new(ssaa_width, ssaa_height));
+
+        let export_uniform_buffer =
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("export_uniform_buffer"),
+                contents: bytemuck::cast_slice(&[export_params]),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+
+        let export_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("export_bind_group"),
+            layout: &shader.render_pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: export_uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: shader.reference_orbit_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: shader.palette_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let texture_format = pixels.surface_texture_format();
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("export_texture"),
+            size: wgpu::Extent3d {
+                width: ssaa_width,
+                height: ssaa_height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: texture_format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let unpadded_bytes_per_row = ssaa_width * 4;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(align) * align;
+
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("export_readback_buffer"),
+            size: (padded_bytes_per_row * ssaa_height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("export_encoder"),
+        });
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("export_render_pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            render_pass.set_pipeline(&shader.render_pipeline);
+            render_pass.set_bind_group(0, &export_bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &readback_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(ssaa_height),
+                },
+            },
+            wgpu::Extent3d {
+                width: ssaa_width,
+                height: ssaa_height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        queue.submit(Some(encoder.finish()));
+
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        readback_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = tx.send(result);
+            });
+
+        device.poll(wgpu::Maintain::Wait);
+        rx.recv().map_err(|_| Error::NoExportChannel)??;
+
+        let ssaa_pixels = {
+            let data = readback_buffer.slice(..).get_mapped_range();
+            let mut ssaa_pixels = vec![0u8; (unpadded_bytes_per_row * ssaa_height) as usize];
+
+            for row in 0..ssaa_height as usize {
+                let src = row * padded_bytes_per_row as usize;
+                let dst = row * unpadded_bytes_per_row as usize;
+
+                ssaa_pixels[dst..dst + unpadded_bytes_per_row as usize]
+                    .copy_from_slice(&data[src..src + unpadded_bytes_per_row as usize]);
+            }
+
+            ssaa_pixels
+        };
+
+        readback_buffer.unmap();
+
+        let is_bgra = matches!(
+            texture_format,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+        );
+
+        let rgba = Self::downsample(&ssaa_pixels, ssaa_width, ssaa_height, samples, is_bgra);
+
+        image::RgbaImage::from_raw(width, height, rgba)
+            .ok_or(Error::NoExportImage)?
+            .save(path)?;
+
+        Ok(())
+    }
+
+    /// Average each `samples`x`samples` block of a supersampled RGBA/BGRA buffer down to a
+    /// single RGBA pixel.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn downsample(
+        pixels: &[u8],
+        ssaa_width: u32,
+        ssaa_height: u32,
+        samples: u32,
+        is_bgra: bool,
+    ) -> Vec<u8> {
+        let width = ssaa_width / samples;
+        let height = ssaa_height / samples;
+
+        let mut output = vec![0u8; (width * height * 4) as usize];
+
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = [0u32; 4];
+
+                for sy in 0..samples {
+                    for sx in 0..samples {
+                        let src_x = x * samples + sx;
+                        let src_y = y * samples + sy;
+                        let index = ((src_y * ssaa_width + src_x) * 4) as usize;
+
+                        for (channel, sum) in sum.iter_mut().enumerate() {
+                            *sum += pixels[index + channel] as u32;
+                        }
+                    }
+                }
+
+                let count = samples * samples;
+                let index = ((y * width + x) * 4) as usize;
+
+                for (channel, sum) in sum.into_iter().enumerate() {
+                    output[index + channel] = (sum / count) as u8;
+                }
+            }
+        }
+
+        if is_bgra {
+            for pixel in output.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        output
+    }
 }
@@ -1,17 +1,41 @@
 use crate::defs;
 
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
 #[derive(Debug)]
 pub struct Camera {
     /// The zoom level of the camera.
-    pub scale: f32,
+    pub scale: f64,
     /// The size of the window.
     pub size: (f32, f32),
     /// The current position of the camera.
-    pub world_position: (f32, f32),
+    pub world_position: (f64, f64),
     /// The current position of the mouse.
     pub mouse_position: (f32, f32),
     /// Whether the mouse is currently pressed.
     pub mouse_pressed: bool,
+    /// Whether the left pan key is currently held.
+    pub is_left_pressed: bool,
+    /// Whether the right pan key is currently held.
+    pub is_right_pressed: bool,
+    /// Whether the up pan key is currently held.
+    pub is_up_pressed: bool,
+    /// Whether the down pan key is currently held.
+    pub is_down_pressed: bool,
+    /// Whether the zoom-in key is currently held.
+    pub is_zoom_in_pressed: bool,
+    /// Whether the zoom-out key is currently held.
+    pub is_zoom_out_pressed: bool,
+    /// World units panned per second at a scale of `1.0`.
+    pub speed: f64,
+    /// Powers of `ZOOM_FACTOR` the scale is divided (zoom in) or multiplied (zoom out) by per
+    /// second while a zoom key is held.
+    pub zoom_speed: f64,
+    /// The time of the last call to [`Camera::update`].
+    last_update: Instant,
 }
 
 impl Camera {
@@ -23,6 +47,15 @@ impl Camera {
             world_position: Default::default(),
             mouse_position: Default::default(),
             mouse_pressed: false,
+            is_left_pressed: false,
+            is_right_pressed: false,
+            is_up_pressed: false,
+            is_down_pressed: false,
+            is_zoom_in_pressed: false,
+            is_zoom_out_pressed: false,
+            speed: defs::PAN_SPEED,
+            zoom_speed: defs::ZOOM_KEY_SPEED,
+            last_update: Instant::now(),
         }
     }
 }
@@ -32,7 +65,7 @@ impl Camera {
     pub fn zoom(&mut self, delta: f32) {
         let (world_x, world_y) = self.mouse_world_position();
 
-        self.scale *= defs::ZOOM_FACTOR.powf(-delta * defs::ZOOM_SENSITIVITY);
+        self.scale *= defs::ZOOM_FACTOR.powf(-delta as f64 * defs::ZOOM_SENSITIVITY);
 
         let (new_world_x, new_world_y) = self.mouse_world_position();
 
@@ -42,29 +75,73 @@ impl Camera {
 
     /// Pan the camera to the given delta.
     pub fn pan(&mut self, delta: (f32, f32)) {
-        let normalized_offset_x = delta.0 / self.size.0;
-        let normalized_offset_y = delta.1 / self.size.1;
+        let normalized_offset_x = delta.0 as f64 / self.size.0 as f64;
+        let normalized_offset_y = delta.1 as f64 / self.size.1 as f64;
 
         let (x, y) = self.position_to_local((normalized_offset_x, normalized_offset_y));
 
         self.world_position.0 -= x;
         self.world_position.1 -= y;
     }
+
+    /// Integrate the currently held keys into the camera's position and scale, scaled by the
+    /// time elapsed since the last call. Returns whether the camera moved (and the scene needs
+    /// to be redrawn) along with that elapsed time, in seconds.
+    pub fn update(&mut self) -> (bool, f64) {
+        let now = Instant::now();
+        let dt = now.duration_since(self.last_update).as_secs_f64();
+        self.last_update = now;
+
+        let mut moved = false;
+
+        let mut pan = (0.0, 0.0);
+
+        if self.is_left_pressed {
+            pan.0 -= 1.0;
+        }
+        if self.is_right_pressed {
+            pan.0 += 1.0;
+        }
+        if self.is_up_pressed {
+            pan.1 -= 1.0;
+        }
+        if self.is_down_pressed {
+            pan.1 += 1.0;
+        }
+
+        if pan != (0.0, 0.0) {
+            self.world_position.0 += pan.0 * self.speed * self.scale * dt;
+            self.world_position.1 += pan.1 * self.speed * self.scale * dt;
+            moved = true;
+        }
+
+        if self.is_zoom_in_pressed {
+            self.scale *= defs::ZOOM_FACTOR.powf(-self.zoom_speed * dt);
+            moved = true;
+        }
+
+        if self.is_zoom_out_pressed {
+            self.scale *= defs::ZOOM_FACTOR.powf(self.zoom_speed * dt);
+            moved = true;
+        }
+
+        (moved, dt)
+    }
 }
 
 impl Camera {
     /// Convert a position in normalized coordinates to local coordinates.
-    fn position_to_local(&self, position: (f32, f32)) -> (f32, f32) {
-        let x = position.0 * self.scale * self.size.0 / self.size.1;
+    fn position_to_local(&self, position: (f64, f64)) -> (f64, f64) {
+        let x = position.0 * self.scale * self.size.0 as f64 / self.size.1 as f64;
         let y = position.1 * self.scale;
 
         (x, y)
     }
 
     /// Get the position of the mouse in world coordinates.
-    fn mouse_world_position(&self) -> (f32, f32) {
-        let normalized_x = self.mouse_position.0 / self.size.0 - 0.5;
-        let normalized_y = self.mouse_position.1 / self.size.1 - 0.5;
+    pub fn mouse_world_position(&self) -> (f64, f64) {
+        let normalized_x = self.mouse_position.0 as f64 / self.size.0 as f64 - 0.5;
+        let normalized_y = self.mouse_position.1 as f64 / self.size.1 as f64 - 0.5;
 
         let (x, y) = self.position_to_local((normalized_x, normalized_y));
 
@@ -4,6 +4,21 @@ pub const MAX_ITER: u32 = 1000;
 pub const START_SCALE: f64 = 4.0;
 pub const ZOOM_FACTOR: f64 = 1.1;
 pub const ZOOM_SENSITIVITY: f64 = 1.0;
+pub const PAN_SPEED: f64 = 0.5;
+pub const ZOOM_KEY_SPEED: f64 = 1.0;
+
+/// Pauldelbrot's glitch criterion threshold: a pixel is considered glitched when
+/// `|Z_n + delta_n|^2 < GLITCH_THRESHOLD * |delta_n|^2`.
+pub const GLITCH_THRESHOLD: f32 = 1e-6;
+
+/// Resolution multiplier applied to the window size for a PNG export.
+pub const EXPORT_SCALE: u32 = 4;
+/// Supersampling factor averaged down per exported pixel.
+pub const EXPORT_SAMPLES: u32 = 2;
+pub const EXPORT_PATH: &str = "fractal_export.png";
+
+pub const FRACTAL_MANDELBROT: u32 = 0;
+pub const FRACTAL_JULIA: u32 = 1;
 
 pub fn init_window() -> winit::window::WindowAttributes {
     winit::window::Window::default_attributes()
@@ -11,32 +26,82 @@ pub fn init_window() -> winit::window::WindowAttributes {
         .with_visible(false)
 }
 
+/// A double-single encoding of an f64 as a pair of f32s, since WGSL (and WebGL2, which the
+/// wasm32 build targets) has no f64 type. `hi` holds the value rounded to f32 and `lo` holds the
+/// rounding error, so the shader can reconstruct extra precision with compensated ("two-sum")
+/// arithmetic instead of collapsing straight back to f32 with a plain `hi + lo`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::NoUninit)]
+pub struct DoubleSingle {
+    hi: f32,
+    lo: f32,
+}
+
+impl DoubleSingle {
+    fn new(value: f64) -> Self {
+        let hi = value as f32;
+        let lo = (value - hi as f64) as f32;
+
+        Self { hi, lo }
+    }
+}
+
+/// Pack a complex double-single value as `(hi.x, lo.x, hi.y, lo.y)` in a single 16-byte
+/// quantity. WGSL's uniform address space requires `array<T, N>` members to have a 16-byte
+/// element stride, which a 2-element array of 8-byte `DoubleSingle`s doesn't satisfy (naga
+/// rejects the shader module outright); a single `vec4<f32>` sidesteps that rule entirely.
+fn pack_double_single2(value: (f64, f64)) -> [f32; 4] {
+    let x = DoubleSingle::new(value.0);
+    let y = DoubleSingle::new(value.1);
+
+    [x.hi, x.lo, y.hi, y.lo]
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Default, bytemuck::NoUninit)]
 pub struct Params {
+    scale: DoubleSingle,
+    /// Padding so `center` lands on the 16-byte boundary WGSL requires for `vec4<f32>`.
+    _scale_pad: [u32; 2],
+    center: [f32; 4],
+    ref_center: [f32; 4],
+    julia_c: [f32; 4],
     max_iter: u32,
-    _padding: [u32; 3],
-    scale: f64,
+    ref_len: u32,
     size: [u32; 2],
-    center: [f64; 2],
+    glitch_threshold: f32,
+    palette_id: u32,
+    palette_speed: f32,
+    color_offset: f32,
+    fractal_kind: u32,
+    /// Padding so the struct's size is a multiple of WGSL's required 16-byte struct alignment.
+    _tail_pad: [u32; 3],
 }
 
 impl Params {
     pub fn new() -> Self {
         Self {
             max_iter: MAX_ITER,
-            scale: START_SCALE,
+            scale: DoubleSingle::new(START_SCALE),
+            glitch_threshold: GLITCH_THRESHOLD,
+            palette_id: crate::palette::GRAYSCALE,
+            palette_speed: crate::palette::DEFAULT_SPEED,
+            fractal_kind: FRACTAL_MANDELBROT,
             ..Default::default()
         }
     }
 
+    pub fn max_iter(&self) -> u32 {
+        self.max_iter
+    }
+
     #[allow(dead_code)]
     pub fn set_max_iter(&mut self, max_iter: u32) {
         self.max_iter = max_iter;
     }
 
     pub fn set_scale(&mut self, scale: f64) {
-        self.scale = scale;
+        self.scale = DoubleSingle::new(scale);
     }
 
     pub fn set_size(&mut self, size: winit::dpi::PhysicalSize<u32>) {
@@ -44,6 +109,49 @@ impl Params {
     }
 
     pub fn set_center(&mut self, center: (f64, f64)) {
-        self.center = [center.0, center.1];
+        self.center = pack_double_single2(center);
+    }
+
+    /// Set the center of the reference orbit used for perturbation rendering.
+    pub fn set_ref_center(&mut self, ref_center: (f64, f64)) {
+        self.ref_center = pack_double_single2(ref_center);
+    }
+
+    /// Set the number of valid entries in the reference orbit buffer.
+    pub fn set_ref_len(&mut self, ref_len: u32) {
+        self.ref_len = ref_len;
+    }
+
+    pub fn palette_id(&self) -> u32 {
+        self.palette_id
+    }
+
+    /// Select one of the built-in gradient palettes (see the [`crate::palette`] constants).
+    pub fn set_palette(&mut self, palette_id: u32) {
+        self.palette_id = palette_id;
+    }
+
+    pub fn color_offset(&self) -> f32 {
+        self.color_offset
+    }
+
+    /// Offset the smooth escape-time index, e.g. to animate color cycling.
+    pub fn set_color_offset(&mut self, color_offset: f32) {
+        self.color_offset = color_offset;
+    }
+
+    pub fn fractal_kind(&self) -> u32 {
+        self.fractal_kind
+    }
+
+    /// Switch between rendering the Mandelbrot set and a Julia set fixed at `julia_c` (see
+    /// [`FRACTAL_MANDELBROT`]/[`FRACTAL_JULIA`]).
+    pub fn set_fractal_kind(&mut self, fractal_kind: u32) {
+        self.fractal_kind = fractal_kind;
+    }
+
+    /// Set the fixed complex constant `c` used when rendering in Julia mode.
+    pub fn set_julia_c(&mut self, julia_c: (f64, f64)) {
+        self.julia_c = pack_double_single2(julia_c);
     }
 }
@@ -13,7 +13,18 @@ pub enum Error {
     NoUniformBuffer,
     /// An error that may be generated when no diffuse bind group is found
     NoBindGroup,
+    /// An error that may be generated when a mapped readback buffer's channel closes early
+    NoExportChannel,
+    /// An error that may be generated when the exported pixel buffer doesn't fit the requested
+    /// image dimensions
+    NoExportImage,
 
+    #[from]
+    /// An error that may be generated when mapping a readback buffer for PNG export
+    BufferAsync(pixels::wgpu::BufferAsyncError),
+    #[from]
+    /// An error that may be generated when encoding or writing a PNG export
+    Image(image::ImageError),
     #[from]
     /// An error that may be generated when requesting Winit state
     Pixels(pixels::Error),
@@ -42,12 +53,16 @@ impl fmt::Display for Error {
             Error::NoRenderPipeline => write!(f, "No render_pipeline field found"),
             Error::NoUniformBuffer => write!(f, "No uniform_buffer field found"),
             Error::NoBindGroup => write!(f, "No bind_group field found"),
+            Error::NoExportChannel => write!(f, "Readback buffer map channel closed unexpectedly"),
+            Error::NoExportImage => write!(f, "Exported pixel buffer did not match the requested image dimensions"),
             Error::Box(e) => write!(f, "{e:?}"),
             Error::Pixels(e) => e.fmt(f),
             Error::Winit(e) => e.fmt(f),
             Error::EventLoop(e) => e.fmt(f),
             Error::Texture(e) => e.fmt(f),
             Error::SetLogger(e) => e.fmt(f),
+            Error::BufferAsync(e) => e.fmt(f),
+            Error::Image(e) => e.fmt(f),
         }
     }
 }
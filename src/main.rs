@@ -4,6 +4,7 @@ mod app;
 mod camera;
 mod defs;
 mod error;
+mod palette;
 mod shader;
 
 use crate::app::App;
@@ -11,14 +12,32 @@ use crate::defs::Result;
 
 use winit::event_loop::{ControlFlow, EventLoop};
 
+#[cfg(not(target_arch = "wasm32"))]
 fn main() -> Result<()> {
     simple_logger::init_with_env()?;
 
-    let event_loop = EventLoop::new()?;
+    run()
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen::prelude::wasm_bindgen(start)]
+pub fn main() {
+    console_error_panic_hook::set_once();
+    console_log::init_with_level(log::Level::Warn).expect("Failed to initialize logger");
+
+    if let Err(e) = run() {
+        log::error!("Failed to run app: {e}");
+    }
+}
+
+/// Build the event loop and hand control to the app. Shared by the native and `wasm32` entry
+/// points above.
+fn run() -> Result<()> {
+    let event_loop = EventLoop::with_user_event().build()?;
 
-    event_loop.set_control_flow(ControlFlow::Wait);
+    event_loop.set_control_flow(ControlFlow::Poll);
 
-    let mut app = App::new(256);
+    let mut app = App::new(256, event_loop.create_proxy());
 
     event_loop.run_app(&mut app)?;
 
@@ -0,0 +1,84 @@
+/// Number of built-in gradient palettes.
+pub const PALETTE_COUNT: u32 = 4;
+/// Number of RGB control points sampled per palette.
+pub const PALETTE_SIZE: usize = 16;
+
+/// Default speed at which the smooth escape-time index cycles through a palette.
+pub const DEFAULT_SPEED: f32 = 1.0;
+
+/// Cycles per second the palette's color offset advances on its own, animating the gradient.
+pub const CYCLE_SPEED: f32 = 0.15;
+
+pub const GRAYSCALE: u32 = 0;
+pub const FIRE: u32 = 1;
+pub const OCEAN: u32 = 2;
+pub const RAINBOW: u32 = 3;
+
+/// Build the flattened `PALETTE_COUNT * PALETTE_SIZE` array of RGBA control points uploaded to
+/// the GPU as a storage buffer. The alpha channel is unused; it keeps each control point 16-byte
+/// aligned for WGSL.
+pub fn control_points() -> Vec<[f32; 4]> {
+    let mut points = Vec::with_capacity(PALETTE_COUNT as usize * PALETTE_SIZE);
+
+    points.extend(gradient(&[[0.0, 0.0, 0.0], [1.0, 1.0, 1.0]]));
+    points.extend(gradient(&[
+        [0.0, 0.0, 0.0],
+        [0.5, 0.0, 0.0],
+        [1.0, 0.4, 0.0],
+        [1.0, 1.0, 0.0],
+        [1.0, 1.0, 1.0],
+    ]));
+    points.extend(gradient(&[
+        [0.0, 0.0, 0.15],
+        [0.0, 0.2, 0.5],
+        [0.0, 0.6, 0.8],
+        [0.6, 1.0, 1.0],
+    ]));
+    points.extend(
+        (0..PALETTE_SIZE).map(|i| {
+            let (r, g, b) = hsv_to_rgb(i as f32 / PALETTE_SIZE as f32, 1.0, 1.0);
+            [r, g, b, 1.0]
+        }),
+    );
+
+    points
+}
+
+/// Resample a handful of key colors into `PALETTE_SIZE` evenly spaced control points.
+fn gradient(keys: &[[f32; 3]]) -> Vec<[f32; 4]> {
+    (0..PALETTE_SIZE)
+        .map(|i| {
+            let t = i as f32 / (PALETTE_SIZE - 1) as f32 * (keys.len() - 1) as f32;
+            let index = t.floor() as usize;
+            let frac = t - index as f32;
+
+            let a = keys[index];
+            let b = keys[(index + 1).min(keys.len() - 1)];
+
+            [
+                a[0] + (b[0] - a[0]) * frac,
+                a[1] + (b[1] - a[1]) * frac,
+                a[2] + (b[2] - a[2]) * frac,
+                1.0,
+            ]
+        })
+        .collect()
+}
+
+/// Convert an HSV color (all components in `0.0..=1.0`) to RGB.
+fn hsv_to_rgb(h: f32, s: f32, v: f32) -> (f32, f32, f32) {
+    let i = (h * 6.0).floor();
+    let f = h * 6.0 - i;
+    let p = v * (1.0 - s);
+    let q = v * (1.0 - f * s);
+    let t = v * (1.0 - (1.0 - f) * s);
+
+    match i as i32 % 6 {
+        0 => (v, t, p),
+        1 => (q, v, p),
+        2 => (p, v, t),
+        3 => (p, q, v),
+        4 => (t, p, v),
+        _ => (v, p, q),
+    }
+}
@@ -1,4 +1,5 @@
 use crate::defs;
+use crate::palette;
 
 use pixels::wgpu;
 use pixels::wgpu::util::DeviceExt as _;
@@ -6,12 +7,23 @@ use wgpu::BindGroup;
 use wgpu::Buffer;
 use wgpu::RenderPipeline;
 
+/// Fraction of the current view scale the camera may drift from the reference orbit's center
+/// before perturbation delta precision degrades enough to be worth recomputing the (expensive,
+/// `MAX_ITER`-iteration f64) orbit over.
+const REFERENCE_ORBIT_DRIFT_THRESHOLD: f64 = 0.25;
+
 pub struct Shader {
     pub render_pipeline: RenderPipeline,
     pub bind_group: BindGroup,
     pub uniform_buffer: Buffer,
+    pub reference_orbit_buffer: Buffer,
+    pub palette_buffer: Buffer,
 
     pub params: defs::Params,
+
+    /// The world-space center the reference orbit was last computed around, kept in full f64
+    /// precision (unlike `params.ref_center`, which is packed into lossy f32 pairs for the GPU).
+    ref_center: (f64, f64),
 }
 
 impl Shader {
@@ -29,21 +41,59 @@ impl Shader {
                     usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
                 });
 
+        let reference_orbit_buffer = pixels.device().create_buffer(&wgpu::BufferDescriptor {
+            label: Some("reference_orbit_buffer"),
+            size: defs::MAX_ITER as u64 * std::mem::size_of::<[f32; 2]>() as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let palette_buffer =
+            pixels
+                .device()
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("palette_buffer"),
+                    contents: bytemuck::cast_slice(&palette::control_points()),
+                    usage: wgpu::BufferUsages::STORAGE,
+                });
+
         let bind_group_layout =
             pixels
                 .device()
                 .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                     label: Some("create_bind_group_layout"),
-                    entries: &[wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Uniform,
-                            has_dynamic_offset: false,
-                            min_binding_size: None,
+                    entries: &[
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Buffer {
+                                ty: wgpu::BufferBindingType::Uniform,
+                                has_dynamic_offset: false,
+                                min_binding_size: None,
+                            },
+                            count: None,
                         },
-                        count: None,
-                    }],
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Buffer {
+                                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                                has_dynamic_offset: false,
+                                min_binding_size: None,
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 2,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Buffer {
+                                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                                has_dynamic_offset: false,
+                                min_binding_size: None,
+                            },
+                            count: None,
+                        },
+                    ],
                 });
 
         let bind_group = pixels
@@ -51,10 +101,20 @@ impl Shader {
             .create_bind_group(&wgpu::BindGroupDescriptor {
                 label: Some("create_bind_group"),
                 layout: &bind_group_layout,
-                entries: &[wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: uniform_buffer.as_entire_binding(),
-                }],
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: reference_orbit_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: palette_buffer.as_entire_binding(),
+                    },
+                ],
             });
 
         let render_pipeline = {
@@ -100,11 +160,73 @@ impl Shader {
                 })
         };
 
-        Self {
+        let mut shader = Self {
             render_pipeline,
             bind_group,
             uniform_buffer,
+            reference_orbit_buffer,
+            palette_buffer,
             params,
+            ref_center: (0.0, 0.0),
+        };
+
+        shader.set_reference_orbit(pixels.queue(), (0.0, 0.0));
+
+        shader
+    }
+}
+
+impl Shader {
+    /// Recompute the high-precision reference orbit for perturbation rendering, centered on
+    /// `center`, and upload it to the GPU.
+    pub fn set_reference_orbit(&mut self, queue: &wgpu::Queue, center: (f64, f64)) {
+        let orbit = Self::reference_orbit(center, self.params.max_iter());
+
+        queue.write_buffer(
+            &self.reference_orbit_buffer,
+            0,
+            bytemuck::cast_slice(&orbit),
+        );
+
+        self.params.set_ref_center(center);
+        self.params.set_ref_len(orbit.len() as u32);
+        self.ref_center = center;
+    }
+
+    /// Whether `center` has drifted far enough from the reference orbit's center, relative to
+    /// `scale`, that perturbation delta precision would degrade — i.e. whether a fresh (costly)
+    /// call to `set_reference_orbit` is actually warranted. Pixel deltas from `ref_center` stay
+    /// accurate for small drifts, so panning/zooming a little doesn't need a new orbit every time.
+    pub fn reference_orbit_stale(&self, center: (f64, f64), scale: f64) -> bool {
+        let dx = center.0 - self.ref_center.0;
+        let dy = center.1 - self.ref_center.1;
+
+        dx.hypot(dy) > scale * REFERENCE_ORBIT_DRIFT_THRESHOLD
+    }
+
+    /// Iterate `Z_{n+1} = Z_n^2 + center` in f64, returning the orbit truncated to f32 for the
+    /// shader's perturbation delta recurrence. Stops early on escape so `ref_len` can bound the
+    /// shader's loop at the true orbit length.
+    fn reference_orbit(center: (f64, f64), max_iter: u32) -> Vec<[f32; 2]> {
+        let (cx, cy) = center;
+        let (mut zx, mut zy) = (0.0_f64, 0.0_f64);
+
+        let mut orbit = Vec::with_capacity(max_iter as usize);
+
+        for _ in 0..max_iter {
+            orbit.push([zx as f32, zy as f32]);
+
+            if zx * zx + zy * zy > 4.0 {
+                break;
+            }
+
+            let next_zx = zx * zx - zy * zy + cx;
+            let next_zy = 2.0 * zx * zy + cy;
+
+            zx = next_zx;
+            zy = next_zy;
         }
+
+        orbit
     }
 }